@@ -0,0 +1,82 @@
+//! AST node definitions produced by the parser.
+//!
+//! Parsing used to emit MC68000 assembly directly as it recognized each
+//! construct. It now builds one of these nodes per construct and returns
+//! it instead, so code generation becomes a separate tree-walk (see
+//! `codegen`) and the parse itself can be inspected, tested, or retargeted.
+
+/// A boolean-or-arithmetic unary operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    /// Arithmetic negation.
+    Neg,
+    /// Boolean negation (`!`).
+    Not,
+}
+
+/// A binary arithmetic operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// A relational operator, from `<relation> ::= <expression> <relop> <expression>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelOp {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+/// A boolean operator joining two conditions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogicOp {
+    And,
+    Or,
+}
+
+/// An expression, as produced by `expression`/`term`/`factor`/`ident`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A literal integer, from `get_num`.
+    Num(i64),
+    /// A variable reference, from `ident`.
+    Var(String),
+    /// A zero-argument function call, from `ident`.
+    Call(String),
+    /// A unary operator applied to an expression.
+    ///
+    /// `UnaryOp::Neg` is not yet constructed by the parser: unary minus is
+    /// still lowered the way the original emitter did it (as `0 - term`).
+    Unary(UnaryOp, Box<Expr>),
+    /// A binary operator applied to two expressions.
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    /// A relational comparison, from `condition`'s `<relation>`.
+    Relation(RelOp, Box<Expr>, Box<Expr>),
+    /// A boolean AND/OR of two conditions, from `condition`'s `<bool-term>`/`<bool-expr>`.
+    Logical(LogicOp, Box<Expr>, Box<Expr>),
+}
+
+/// A statement, as produced by `block` and the `do_*` constructs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// A placeholder statement recognized by `other`: a single identifier
+    /// with no assignment, kept from the original stand-in for "some
+    /// statement we haven't implemented yet".
+    Other(String),
+    Assign(String, Expr),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>),
+    While(Expr, Box<Stmt>),
+    Loop(Box<Stmt>),
+    Repeat(Box<Stmt>, Expr),
+    For(String, Expr, Expr, Box<Stmt>),
+    Do(Expr, Box<Stmt>),
+    Break,
+    Block(Vec<Stmt>),
+}
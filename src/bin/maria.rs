@@ -1,11 +1,45 @@
-use maria::{self, Cradle};
-
-fn main() {
-    let stdio = std::io::stdin();
-    let input = stdio.lock();
-    let mut c = Cradle::new(input);
-    c.assignment();
-    if c.look != '\n' {
-        maria::expected("Newline");
+use std::env;
+use std::io::{self, BufRead};
+use std::process::ExitCode;
+
+use maria::codegen::{self, Target};
+use maria::{vm, Cradle, Diagnostic};
+
+/// Parse a program from stdin and either run it or emit code for it.
+///
+/// With no arguments this emits MC68000 assembly, the original behavior.
+/// `--llvm` emits LLVM IR instead, and `--eval` runs the program on the
+/// stack VM and prints the resulting value.
+fn main() -> ExitCode {
+    let target = match env::args().nth(1).as_deref() {
+        None => Some(Target::Mc68000),
+        Some("--llvm") => Some(Target::Llvm),
+        Some("--eval") => None,
+        Some(other) => {
+            eprintln!("unknown option: {other} (expected --llvm or --eval)");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let stdin = io::stdin();
+    let program = match parse(stdin.lock()) {
+        Ok(program) => program,
+        Err(diag) => return fail(&diag),
+    };
+
+    match target {
+        Some(target) => codegen::generate(target, &program, io::stdout()),
+        None => println!("{}", vm::eval(&program)),
     }
+    ExitCode::SUCCESS
+}
+
+fn parse<R: BufRead>(input: R) -> Result<maria::ast::Stmt, Diagnostic> {
+    let mut cradle = Cradle::new(input)?;
+    cradle.do_program()
+}
+
+fn fail(diag: &Diagnostic) -> ExitCode {
+    eprintln!("{diag}");
+    ExitCode::FAILURE
 }
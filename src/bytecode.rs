@@ -0,0 +1,296 @@
+//! Stack-bytecode backend.
+//!
+//! Compiles the `ast` tree to a small stack-machine instruction set that
+//! `vm` can execute directly, so `maria` can evaluate a program instead of
+//! only emitting MC68000 mnemonics for it. Variables are resolved to
+//! numbered slots at compile time, the same way the MC68000 codegen
+//! resolves them to named memory locations.
+
+use std::collections::HashMap;
+
+use crate::ast::{self, BinOp, Expr, LogicOp, Stmt, UnaryOp};
+
+/// A relational operator, used by `Instr::Cmp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelOp {
+    Eq,
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+}
+
+impl From<ast::RelOp> for RelOp {
+    fn from(op: ast::RelOp) -> Self {
+        match op {
+            ast::RelOp::Eq => RelOp::Eq,
+            ast::RelOp::Ne => RelOp::Ne,
+            ast::RelOp::Lt => RelOp::Lt,
+            ast::RelOp::Gt => RelOp::Gt,
+            ast::RelOp::Le => RelOp::Le,
+            ast::RelOp::Ge => RelOp::Ge,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instr {
+    PushInt(i64),
+    Load(usize),
+    Store(usize),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Cmp(RelOp),
+    And,
+    Or,
+    Not,
+    Jump(usize),
+    JumpUnless(usize),
+    Call(usize),
+    Ret,
+}
+
+/// Compiles an `ast::Stmt` into a flat `Vec<Instr>`.
+///
+/// Control constructs compile the way they do in the MC68000 backend: a
+/// condition leaves 0/1 on the stack, `JumpUnless` skips past the body
+/// when it's false, and loops close with a backward `Jump`.
+pub struct Compiler {
+    code: Vec<Instr>,
+    slots: HashMap<String, usize>,
+    next_slot: usize,
+    break_patches: Vec<Vec<usize>>,
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Compiler {
+            code: Vec::new(),
+            slots: HashMap::new(),
+            next_slot: 0,
+            break_patches: Vec::new(),
+        }
+    }
+
+    /// Compile a whole program, terminating it with a `Ret`.
+    pub fn compile(&mut self, program: &Stmt) -> Vec<Instr> {
+        self.compile_stmt(program);
+        self.code.push(Instr::Ret);
+        self.code.clone()
+    }
+
+    /// Number of variable/temporary slots a `Vm` needs to run this program.
+    pub fn slot_count(&self) -> usize {
+        self.next_slot
+    }
+
+    /// The slot a named variable was assigned, if `compile` has seen it.
+    ///
+    /// No statement leaves a value on the `Vm`'s stack when it halts, so
+    /// this is how a caller (or a test) reads back what a variable ended
+    /// up holding after a run.
+    pub fn slot_of(&self, name: &str) -> Option<usize> {
+        self.slots.get(name).copied()
+    }
+
+    fn slot(&mut self, name: &str) -> usize {
+        if let Some(&s) = self.slots.get(name) {
+            return s;
+        }
+        let s = self.next_slot;
+        self.next_slot += 1;
+        self.slots.insert(name.to_string(), s);
+        s
+    }
+
+    fn temp_slot(&mut self) -> usize {
+        let s = self.next_slot;
+        self.next_slot += 1;
+        s
+    }
+
+    fn emit(&mut self, instr: Instr) -> usize {
+        self.code.push(instr);
+        self.code.len() - 1
+    }
+
+    fn patch_jump(&mut self, at: usize, target: usize) {
+        match &mut self.code[at] {
+            Instr::Jump(addr) | Instr::JumpUnless(addr) => *addr = target,
+            _ => panic!("patch_jump called on a non-jump instruction"),
+        }
+    }
+
+    fn compile_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Num(n) => {
+                self.emit(Instr::PushInt(*n));
+            }
+            Expr::Var(name) => {
+                let slot = self.slot(name);
+                self.emit(Instr::Load(slot));
+            }
+            Expr::Call(_name) => {
+                // The parser only recognizes call *sites*; there's no
+                // function-definition syntax yet to give a `Call` a real
+                // target address.
+                panic!("calls to undefined functions are not supported yet");
+            }
+            Expr::Unary(UnaryOp::Neg, inner) => {
+                self.emit(Instr::PushInt(0));
+                self.compile_expr(inner);
+                self.emit(Instr::Sub);
+            }
+            Expr::Unary(UnaryOp::Not, inner) => {
+                self.compile_expr(inner);
+                self.emit(Instr::Not);
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                self.compile_expr(lhs);
+                self.compile_expr(rhs);
+                self.emit(match op {
+                    BinOp::Add => Instr::Add,
+                    BinOp::Sub => Instr::Sub,
+                    BinOp::Mul => Instr::Mul,
+                    BinOp::Div => Instr::Div,
+                });
+            }
+            Expr::Relation(op, lhs, rhs) => {
+                self.compile_expr(lhs);
+                self.compile_expr(rhs);
+                self.emit(Instr::Cmp((*op).into()));
+            }
+            Expr::Logical(op, lhs, rhs) => {
+                self.compile_expr(lhs);
+                self.compile_expr(rhs);
+                self.emit(match op {
+                    LogicOp::And => Instr::And,
+                    LogicOp::Or => Instr::Or,
+                });
+            }
+        }
+    }
+
+    fn compile_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Other(_) => {}
+            Stmt::Assign(name, expr) => {
+                self.compile_expr(expr);
+                let slot = self.slot(name);
+                self.emit(Instr::Store(slot));
+            }
+            Stmt::Block(stmts) => {
+                for s in stmts {
+                    self.compile_stmt(s);
+                }
+            }
+            Stmt::If(cond, then_branch, else_branch) => {
+                self.compile_expr(cond);
+                let skip_then = self.emit(Instr::JumpUnless(0));
+                self.compile_stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    let skip_else = self.emit(Instr::Jump(0));
+                    self.patch_jump(skip_then, self.code.len());
+                    self.compile_stmt(else_branch);
+                    self.patch_jump(skip_else, self.code.len());
+                } else {
+                    self.patch_jump(skip_then, self.code.len());
+                }
+            }
+            Stmt::While(cond, body) => {
+                let top = self.code.len();
+                self.compile_expr(cond);
+                let exit = self.emit(Instr::JumpUnless(0));
+                self.break_patches.push(Vec::new());
+                self.compile_stmt(body);
+                self.emit(Instr::Jump(top));
+                self.patch_jump(exit, self.code.len());
+                self.patch_breaks();
+            }
+            Stmt::Loop(body) => {
+                let top = self.code.len();
+                self.break_patches.push(Vec::new());
+                self.compile_stmt(body);
+                self.emit(Instr::Jump(top));
+                self.patch_breaks();
+            }
+            Stmt::Repeat(body, cond) => {
+                let top = self.code.len();
+                self.break_patches.push(Vec::new());
+                self.compile_stmt(body);
+                self.compile_expr(cond);
+                self.emit(Instr::JumpUnless(top));
+                self.patch_breaks();
+            }
+            Stmt::For(name, start, end, body) => {
+                self.compile_expr(start);
+                let slot = self.slot(name);
+                self.emit(Instr::Store(slot));
+
+                let top = self.code.len();
+                self.emit(Instr::Load(slot));
+                self.compile_expr(end);
+                self.emit(Instr::Cmp(RelOp::Le));
+                let exit = self.emit(Instr::JumpUnless(0));
+
+                self.break_patches.push(Vec::new());
+                self.compile_stmt(body);
+
+                self.emit(Instr::Load(slot));
+                self.emit(Instr::PushInt(1));
+                self.emit(Instr::Add);
+                self.emit(Instr::Store(slot));
+                self.emit(Instr::Jump(top));
+
+                self.patch_jump(exit, self.code.len());
+                self.patch_breaks();
+            }
+            Stmt::Do(count, body) => {
+                self.compile_expr(count);
+                let counter = self.temp_slot();
+                self.emit(Instr::Store(counter));
+
+                let top = self.code.len();
+                self.emit(Instr::Load(counter));
+                self.emit(Instr::PushInt(0));
+                self.emit(Instr::Cmp(RelOp::Gt));
+                let exit = self.emit(Instr::JumpUnless(0));
+
+                self.break_patches.push(Vec::new());
+                self.compile_stmt(body);
+
+                self.emit(Instr::Load(counter));
+                self.emit(Instr::PushInt(1));
+                self.emit(Instr::Sub);
+                self.emit(Instr::Store(counter));
+                self.emit(Instr::Jump(top));
+
+                self.patch_jump(exit, self.code.len());
+                self.patch_breaks();
+            }
+            Stmt::Break => {
+                let jump = self.emit(Instr::Jump(0));
+                self.break_patches
+                    .last_mut()
+                    .expect("Break outside of a loop")
+                    .push(jump);
+            }
+        }
+    }
+
+    fn patch_breaks(&mut self) {
+        let after = self.code.len();
+        for jump in self.break_patches.pop().expect("unbalanced loop tracking") {
+            self.patch_jump(jump, after);
+        }
+    }
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,536 @@
+//! Code generation: walks the `ast` tree and emits target code.
+//!
+//! This used to be a single `Mc68000` struct that hard-coded `print!`s of
+//! MC68000 mnemonics as it recognized each construct. The tree walk is now
+//! shared (`Walker`) and backend-specific, emits live behind a `Backend`
+//! trait, so retargeting means writing a new `Backend` impl instead of a
+//! new copy of the walk. Every backend writes through a generic `W: Write`
+//! sink instead of stdout, so output can be captured (a `Vec<u8>`, a file,
+//! a test buffer) instead of only ever going to the terminal.
+
+use std::collections::HashSet;
+use std::io::Write;
+
+use crate::ast::{BinOp, Expr, LogicOp, RelOp, Stmt, UnaryOp};
+
+/// The primitive operations a codegen target needs to support.
+///
+/// `Value` is whatever a backend uses to refer to the result of an
+/// emitted expression: the 68000 backend has no real values (everything
+/// lives in `D0`, by convention, after `Walker` emits an expression), so
+/// its `Value` is `()`; the LLVM backend's `Value` is an SSA operand
+/// (a register name or an inline literal).
+pub trait Backend {
+    type Value;
+
+    fn emit_const(&mut self, n: i64) -> Self::Value;
+    fn emit_load(&mut self, name: &str) -> Self::Value;
+    fn emit_store(&mut self, name: &str, value: Self::Value);
+    fn emit_call(&mut self, name: &str) -> Self::Value;
+    fn emit_neg(&mut self, value: Self::Value) -> Self::Value;
+    fn emit_not(&mut self, value: Self::Value) -> Self::Value;
+    fn emit_binop(&mut self, op: BinOp, lhs: Self::Value, rhs: Self::Value) -> Self::Value;
+    fn emit_relop(&mut self, op: RelOp, lhs: Self::Value, rhs: Self::Value) -> Self::Value;
+    fn emit_logicop(&mut self, op: LogicOp, lhs: Self::Value, rhs: Self::Value) -> Self::Value;
+
+    /// Keep `value` usable across the emission of another expression.
+    ///
+    /// The 68000 backend only ever has one live value (`D0`), so
+    /// evaluating a right-hand side clobbers a left-hand side that hasn't
+    /// been saved first; this is its chance to push `D0` to the stack.
+    /// SSA values need no help, so the LLVM backend's impl is the identity.
+    fn hold(&mut self, value: Self::Value) -> Self::Value;
+
+    /// A fresh, unique label for a branch target.
+    fn new_label(&mut self) -> String;
+    /// A fresh, unique variable name for a loop counter with no source name.
+    fn new_temp(&mut self) -> String;
+
+    fn emit_label(&mut self, label: &str);
+    fn emit_branch(&mut self, label: &str);
+    fn emit_branch_if_false(&mut self, value: Self::Value, label: &str);
+
+    /// Emit whatever trails the last statement (an `END` directive, a
+    /// function epilogue, ...).
+    fn finish(&mut self);
+}
+
+/// Walks an `ast::Stmt`/`ast::Expr` tree, translating it into `Backend`
+/// calls in emission order.
+///
+/// `For` and `Do` are lowered into the same primitives as every other
+/// construct (a named/temporary counter variable, a relational condition,
+/// a conditional branch) rather than backend-specific loop instructions,
+/// so both backends run them with the same semantics.
+pub struct Walker<'a, B: Backend> {
+    backend: &'a mut B,
+}
+
+impl<'a, B: Backend> Walker<'a, B> {
+    pub fn new(backend: &'a mut B) -> Self {
+        Walker { backend }
+    }
+
+    /// Generate a whole program: the body, followed by `Backend::finish`.
+    pub fn gen_program(&mut self, program: &Stmt) {
+        self.gen_stmt(program, None);
+        self.backend.finish();
+    }
+
+    fn gen_expr(&mut self, expr: &Expr) -> B::Value {
+        match expr {
+            Expr::Num(n) => self.backend.emit_const(*n),
+            Expr::Var(name) => self.backend.emit_load(name),
+            Expr::Call(name) => self.backend.emit_call(name),
+            Expr::Unary(UnaryOp::Neg, inner) => {
+                let v = self.gen_expr(inner);
+                self.backend.emit_neg(v)
+            }
+            Expr::Unary(UnaryOp::Not, inner) => {
+                let v = self.gen_expr(inner);
+                self.backend.emit_not(v)
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.gen_expr(lhs);
+                let lhs = self.backend.hold(lhs);
+                let rhs = self.gen_expr(rhs);
+                self.backend.emit_binop(*op, lhs, rhs)
+            }
+            Expr::Relation(op, lhs, rhs) => {
+                let lhs = self.gen_expr(lhs);
+                let lhs = self.backend.hold(lhs);
+                let rhs = self.gen_expr(rhs);
+                self.backend.emit_relop(*op, lhs, rhs)
+            }
+            Expr::Logical(op, lhs, rhs) => {
+                let lhs = self.gen_expr(lhs);
+                let lhs = self.backend.hold(lhs);
+                let rhs = self.gen_expr(rhs);
+                self.backend.emit_logicop(*op, lhs, rhs)
+            }
+        }
+    }
+
+    fn gen_stmt(&mut self, stmt: &Stmt, break_label: Option<&str>) {
+        match stmt {
+            Stmt::Other(_) => {}
+            Stmt::Assign(name, expr) => {
+                let v = self.gen_expr(expr);
+                self.backend.emit_store(name, v);
+            }
+            Stmt::Block(stmts) => {
+                for s in stmts {
+                    self.gen_stmt(s, break_label);
+                }
+            }
+            Stmt::If(cond, then_branch, else_branch) => {
+                let c = self.gen_expr(cond);
+                let l1 = self.backend.new_label();
+                self.backend.emit_branch_if_false(c, &l1);
+                self.gen_stmt(then_branch, break_label);
+                if let Some(else_branch) = else_branch {
+                    let l2 = self.backend.new_label();
+                    self.backend.emit_branch(&l2);
+                    self.backend.emit_label(&l1);
+                    self.gen_stmt(else_branch, break_label);
+                    self.backend.emit_label(&l2);
+                } else {
+                    self.backend.emit_label(&l1);
+                }
+            }
+            Stmt::While(cond, body) => {
+                let l1 = self.backend.new_label();
+                let l2 = self.backend.new_label();
+                self.backend.emit_label(&l1);
+                let c = self.gen_expr(cond);
+                self.backend.emit_branch_if_false(c, &l2);
+                self.gen_stmt(body, Some(&l2));
+                self.backend.emit_branch(&l1);
+                self.backend.emit_label(&l2);
+            }
+            Stmt::Loop(body) => {
+                let l1 = self.backend.new_label();
+                let l2 = self.backend.new_label();
+                self.backend.emit_label(&l1);
+                self.gen_stmt(body, Some(&l2));
+                self.backend.emit_branch(&l1);
+                self.backend.emit_label(&l2);
+            }
+            Stmt::Repeat(body, cond) => {
+                let l1 = self.backend.new_label();
+                let l2 = self.backend.new_label();
+                self.backend.emit_label(&l1);
+                self.gen_stmt(body, Some(&l2));
+                let c = self.gen_expr(cond);
+                self.backend.emit_branch_if_false(c, &l1);
+                self.backend.emit_label(&l2);
+            }
+            Stmt::For(name, start, end, body) => {
+                let start_v = self.gen_expr(start);
+                self.backend.emit_store(name, start_v);
+
+                let l1 = self.backend.new_label();
+                let l2 = self.backend.new_label();
+                self.backend.emit_label(&l1);
+                let cond = Expr::Relation(
+                    RelOp::Le,
+                    Box::new(Expr::Var(name.clone())),
+                    Box::new(end.clone()),
+                );
+                let c = self.gen_expr(&cond);
+                self.backend.emit_branch_if_false(c, &l2);
+                self.gen_stmt(body, Some(&l2));
+                let incr = Expr::Binary(
+                    BinOp::Add,
+                    Box::new(Expr::Var(name.clone())),
+                    Box::new(Expr::Num(1)),
+                );
+                let v = self.gen_expr(&incr);
+                self.backend.emit_store(name, v);
+                self.backend.emit_branch(&l1);
+                self.backend.emit_label(&l2);
+            }
+            Stmt::Do(count, body) => {
+                let counter = self.backend.new_temp();
+                let count_v = self.gen_expr(count);
+                self.backend.emit_store(&counter, count_v);
+
+                let l1 = self.backend.new_label();
+                let l2 = self.backend.new_label();
+                self.backend.emit_label(&l1);
+                let cond = Expr::Relation(
+                    RelOp::Gt,
+                    Box::new(Expr::Var(counter.clone())),
+                    Box::new(Expr::Num(0)),
+                );
+                let c = self.gen_expr(&cond);
+                self.backend.emit_branch_if_false(c, &l2);
+                self.gen_stmt(body, Some(&l2));
+                let decr = Expr::Binary(
+                    BinOp::Sub,
+                    Box::new(Expr::Var(counter.clone())),
+                    Box::new(Expr::Num(1)),
+                );
+                let v = self.gen_expr(&decr);
+                self.backend.emit_store(&counter, v);
+                self.backend.emit_branch(&l1);
+                self.backend.emit_label(&l2);
+            }
+            Stmt::Break => {
+                let label = break_label.expect("Break outside of a loop");
+                self.backend.emit_branch(label);
+            }
+        }
+    }
+}
+
+/// MC68000 backend: emits the same mnemonics the parser used to emit
+/// inline, now through a `W: Write` sink instead of stdout. There's
+/// nowhere to stash a real value between expressions (everything lives in
+/// `D0`), so `Value` is `()` and every `Backend` method operates on `D0`
+/// and the stack the way the original inline emitter did.
+pub struct Mc68000<W> {
+    out: W,
+    lcount: usize,
+    tcount: usize,
+}
+
+/// `TAB` moved to `lexer` along with the rest of whitespace handling;
+/// `Mc68000` only ever used it to indent emitted lines, so it keeps its
+/// own copy instead of reaching into another module for one character.
+const TAB: char = '\t';
+
+impl<W: Write> Mc68000<W> {
+    pub fn new(out: W) -> Self {
+        Mc68000 {
+            out,
+            lcount: 0,
+            tcount: 0,
+        }
+    }
+
+    fn emit(&mut self, s: &str) {
+        write!(self.out, "{}", TAB.to_string() + s).expect("write to codegen sink failed");
+    }
+
+    fn emitln(&mut self, s: &str) {
+        self.emit(s);
+        writeln!(self.out).expect("write to codegen sink failed");
+    }
+
+    fn post_label(&mut self, label: &str) {
+        writeln!(self.out, "{}:", label).expect("write to codegen sink failed");
+    }
+}
+
+impl<W: Write> Backend for Mc68000<W> {
+    type Value = ();
+
+    fn emit_const(&mut self, n: i64) {
+        self.emitln(&format!("MOVE #{},D0", n));
+    }
+
+    fn emit_load(&mut self, name: &str) {
+        self.emitln(&format!("MOVE {}(PC),D0", name));
+    }
+
+    fn emit_store(&mut self, name: &str, _value: ()) {
+        self.emitln(&format!("LEA {}(PC),A0", name));
+        self.emitln("MOVE D0,(A0)");
+    }
+
+    fn emit_call(&mut self, name: &str) {
+        self.emitln(&format!("BSR {}", name));
+    }
+
+    fn emit_neg(&mut self, _value: ()) {
+        self.emitln("NEG D0");
+    }
+
+    fn emit_not(&mut self, _value: ()) {
+        self.emitln("NOT D0");
+    }
+
+    fn emit_binop(&mut self, op: BinOp, _lhs: (), _rhs: ()) {
+        match op {
+            BinOp::Add => self.emitln("ADD (SP)+,D0"),
+            BinOp::Sub => {
+                self.emitln("SUB (SP)+,D0");
+                self.emitln("NEG D0");
+            }
+            BinOp::Mul => self.emitln("MULS (SP)+,D0"),
+            BinOp::Div => {
+                self.emitln("MOVE (SP)+,D1");
+                self.emitln("DIVS D1,D0");
+            }
+        }
+    }
+
+    fn emit_relop(&mut self, op: RelOp, _lhs: (), _rhs: ()) {
+        self.emitln("CMP (SP)+,D0");
+        // `hold` pushed lhs and rhs landed in D0, so `CMP (SP)+,D0` computes
+        // D0 - (SP), i.e. rhs - lhs: the set-mnemonics below are the
+        // Lt/Gt- and Le/Ge-swapped ones for exactly that reason, the same
+        // compensation Crenshaw's own Less/Greater routines make.
+        self.emitln(match op {
+            RelOp::Eq => "SEQ D0",
+            RelOp::Ne => "SNE D0",
+            RelOp::Lt => "SGT D0",
+            RelOp::Gt => "SLT D0",
+            RelOp::Le => "SGE D0",
+            RelOp::Ge => "SLE D0",
+        });
+    }
+
+    fn emit_logicop(&mut self, op: LogicOp, _lhs: (), _rhs: ()) {
+        match op {
+            LogicOp::And => self.emitln("AND (SP)+,D0"),
+            LogicOp::Or => self.emitln("OR (SP)+,D0"),
+        }
+    }
+
+    fn hold(&mut self, _value: ()) {
+        self.emitln("MOVE D0,-(SP)");
+    }
+
+    fn new_label(&mut self) -> String {
+        let label = format!("L{}", self.lcount);
+        self.lcount += 1;
+        label
+    }
+
+    fn new_temp(&mut self) -> String {
+        let name = format!("_t{}", self.tcount);
+        self.tcount += 1;
+        name
+    }
+
+    fn emit_label(&mut self, label: &str) {
+        self.post_label(label);
+    }
+
+    fn emit_branch(&mut self, label: &str) {
+        self.emitln(&format!("BRA {}", label));
+    }
+
+    fn emit_branch_if_false(&mut self, _value: (), label: &str) {
+        self.emitln(&format!("BEQ {}", label));
+    }
+
+    fn finish(&mut self) {
+        self.emitln("END");
+    }
+}
+
+/// LLVM-IR backend: lowers the same tree to a single textual `@main`
+/// function, SSA-style. Every source variable becomes an `alloca` (on
+/// first use) rather than an SSA register directly, the same simplifying
+/// choice most non-optimizing front ends make and leave to `mem2reg`.
+pub struct LlvmBackend<W> {
+    out: W,
+    next_value: usize,
+    next_label: usize,
+    declared: HashSet<String>,
+}
+
+impl<W: Write> LlvmBackend<W> {
+    pub fn new(mut out: W) -> Self {
+        writeln!(out, "define i64 @main() {{").expect("write to codegen sink failed");
+        writeln!(out, "entry:").expect("write to codegen sink failed");
+        LlvmBackend {
+            out,
+            next_value: 0,
+            next_label: 0,
+            declared: HashSet::new(),
+        }
+    }
+
+    fn fresh(&mut self) -> String {
+        let v = format!("%v{}", self.next_value);
+        self.next_value += 1;
+        v
+    }
+
+    fn line(&mut self, s: &str) {
+        writeln!(self.out, "  {}", s).expect("write to codegen sink failed");
+    }
+
+    /// Emit the `alloca` for `name` the first time it's referenced.
+    fn ensure_declared(&mut self, name: &str) {
+        if self.declared.insert(name.to_string()) {
+            writeln!(self.out, "  %{} = alloca i64", name).expect("write to codegen sink failed");
+        }
+    }
+}
+
+impl<W: Write> Backend for LlvmBackend<W> {
+    /// An SSA operand: either a register name (`%vN`) or an inline
+    /// integer literal, so constants don't need a useless `add i64 0, n`.
+    type Value = String;
+
+    fn emit_const(&mut self, n: i64) -> String {
+        n.to_string()
+    }
+
+    fn emit_load(&mut self, name: &str) -> String {
+        self.ensure_declared(name);
+        let v = self.fresh();
+        self.line(&format!("{} = load i64, i64* %{}", v, name));
+        v
+    }
+
+    fn emit_store(&mut self, name: &str, value: String) {
+        self.ensure_declared(name);
+        self.line(&format!("store i64 {}, i64* %{}", value, name));
+    }
+
+    fn emit_call(&mut self, name: &str) -> String {
+        let v = self.fresh();
+        self.line(&format!("{} = call i64 @{}()", v, name));
+        v
+    }
+
+    fn emit_neg(&mut self, value: String) -> String {
+        let v = self.fresh();
+        self.line(&format!("{} = sub i64 0, {}", v, value));
+        v
+    }
+
+    fn emit_not(&mut self, value: String) -> String {
+        let v = self.fresh();
+        self.line(&format!("{} = xor i64 {}, 1", v, value));
+        v
+    }
+
+    fn emit_binop(&mut self, op: BinOp, lhs: String, rhs: String) -> String {
+        let instr = match op {
+            BinOp::Add => "add",
+            BinOp::Sub => "sub",
+            BinOp::Mul => "mul",
+            BinOp::Div => "sdiv",
+        };
+        let v = self.fresh();
+        self.line(&format!("{} = {} i64 {}, {}", v, instr, lhs, rhs));
+        v
+    }
+
+    fn emit_relop(&mut self, op: RelOp, lhs: String, rhs: String) -> String {
+        let cond = match op {
+            RelOp::Eq => "eq",
+            RelOp::Ne => "ne",
+            RelOp::Lt => "slt",
+            RelOp::Gt => "sgt",
+            RelOp::Le => "sle",
+            RelOp::Ge => "sge",
+        };
+        let bit = self.fresh();
+        self.line(&format!("{} = icmp {} i64 {}, {}", bit, cond, lhs, rhs));
+        let v = self.fresh();
+        self.line(&format!("{} = zext i1 {} to i64", v, bit));
+        v
+    }
+
+    fn emit_logicop(&mut self, op: LogicOp, lhs: String, rhs: String) -> String {
+        let instr = match op {
+            LogicOp::And => "and",
+            LogicOp::Or => "or",
+        };
+        let v = self.fresh();
+        self.line(&format!("{} = {} i64 {}, {}", v, instr, lhs, rhs));
+        v
+    }
+
+    fn hold(&mut self, value: String) -> String {
+        value
+    }
+
+    fn new_label(&mut self) -> String {
+        let label = format!("L{}", self.next_label);
+        self.next_label += 1;
+        label
+    }
+
+    fn new_temp(&mut self) -> String {
+        // Loop counters are just unnamed variables here: `name` only has
+        // to be unique, the same as every other `alloca`'d variable.
+        self.fresh().trim_start_matches('%').to_string()
+    }
+
+    fn emit_label(&mut self, label: &str) {
+        writeln!(self.out, "{}:", label).expect("write to codegen sink failed");
+    }
+
+    fn emit_branch(&mut self, label: &str) {
+        self.line(&format!("br label %{}", label));
+    }
+
+    fn emit_branch_if_false(&mut self, value: String, label: &str) {
+        let bit = self.fresh();
+        self.line(&format!("{} = icmp ne i64 {}, 0", bit, value));
+        let cont = self.new_label();
+        self.line(&format!("br i1 {}, label %{}, label %{}", bit, cont, label));
+        self.emit_label(&cont);
+    }
+
+    fn finish(&mut self) {
+        self.line("ret i64 0");
+        writeln!(self.out, "}}").expect("write to codegen sink failed");
+    }
+}
+
+/// The codegen target `generate` should emit, selectable at runtime
+/// (e.g. from a CLI flag), since picking a `Backend` impl at compile time
+/// would mean picking a `generate` monomorphization per target instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Mc68000,
+    Llvm,
+}
+
+/// Generate `program` for `target`, writing through `out`.
+pub fn generate<W: Write>(target: Target, program: &Stmt, out: W) {
+    match target {
+        Target::Mc68000 => Walker::new(&mut Mc68000::new(out)).gen_program(program),
+        Target::Llvm => Walker::new(&mut LlvmBackend::new(out)).gen_program(program),
+    }
+}
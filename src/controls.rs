@@ -1,149 +1,100 @@
-use std::io::{BufRead, Read};
-
-// Constant declarations
-pub const TAB: char = '\t';
-pub const NEW_LINE: char = '\n';
-pub const SPACE: char = ' ';
-
-#[derive(Debug, PartialEq, Eq)]
-enum Ops {
-    ADD,
-    SUB,
-    MUL,
-    DIV,
-    INVALID,
-}
+use std::io::BufRead;
 
-impl From<char> for Ops {
-    fn from(c: char) -> Self {
-        match c {
-            '+' => Ops::ADD,
-            '-' => Ops::SUB,
-            '*' => Ops::MUL,
-            '/' => Ops::DIV,
-            _ => Ops::INVALID,
-        }
-    }
-}
+use crate::ast::{BinOp, Expr, LogicOp, RelOp, Stmt, UnaryOp};
+use crate::diagnostics::Diagnostic;
+use crate::lexer::{Keyword, Lexer, Op, Token};
 
-/// Cradle contains the lookahead character
-/// and Input that implements Read trait
-/// This way it helps testing with dependency injection
+/// Cradle holds the lookahead token and the lexer producing the stream.
+///
+/// This used to hold the lookahead *character* and read straight from the
+/// `Read` stream itself; tokenizing is now `lexer`'s job, so parsing works
+/// with a single-token lookahead instead.
 pub struct Cradle<R> {
-    /// Lookahead character
-    pub look: char,
-
-    /// Input reader
-    pub input: R,
+    /// Lookahead token
+    pub look: Token,
 
-    /// Label count, used in control statements
-    pub lcount: usize,
+    lexer: Lexer<R>,
 }
 
 impl<R: BufRead> Cradle<R> {
-    pub fn new(input: R) -> Self {
-        let mut cradle = Cradle {
-            look: '2',
-            input,
-            lcount: 0,
-        };
-        cradle.look = cradle.get_char();
-        cradle.other();
-        cradle
-    }
-
-    /// Get character
-    pub fn get_char(&mut self) -> char {
-        // TODO: don't use unwrap
-        self.input
-            .by_ref()
-            .bytes()
-            .next()
-            .map(|b| b.ok().unwrap() as char)
-            .unwrap()
-    }
-
-    /// Skip over leading White Space
-    pub fn skip_white(&mut self) {
-        while self.is_white() {
-            self.look = self.get_char();
+    pub fn new(input: R) -> Result<Self, Diagnostic> {
+        let mut lexer = Lexer::new(input);
+        let look = lexer.next_token()?;
+        Ok(Cradle { look, lexer })
+    }
+
+    /// Advance the lookahead to the next token.
+    fn advance(&mut self) -> Result<(), Diagnostic> {
+        self.look = self.lexer.next_token()?;
+        Ok(())
+    }
+
+    /// Recognize a statement that isn't one of the known control
+    /// constructs yet (still just a bare identifier, as in the original
+    /// stand-in for "some statement we haven't implemented").
+    pub fn other(&mut self) -> Result<Stmt, Diagnostic> {
+        let name = self.get_name()?;
+        Ok(Stmt::Other(name))
+    }
+
+    /// Recognize a statement starting with an identifier: either an
+    /// `Assign` (`name = expr`) or, failing that, `other`'s placeholder.
+    /// The lookahead is only one token deep, so this consumes the name
+    /// first and then decides what follows it, rather than backtracking.
+    pub fn ident_stmt(&mut self) -> Result<Stmt, Diagnostic> {
+        let name = self.get_name()?;
+        if self.look == Token::Assign {
+            self.advance()?;
+            let expr = self.expression()?;
+            Ok(Stmt::Assign(name, expr))
+        } else {
+            Ok(Stmt::Other(name))
         }
     }
 
-    /// Returns true if Lookahead character is TAB or SPACE
-    pub fn is_white(&mut self) -> bool {
-        [TAB, SPACE].iter().any(|w| *w == self.look)
-    }
-
-    /// Recognize and Translate an "Other"
-    pub fn other(&mut self) {
-        let name = self.get_name();
-        self.emitln(&name.to_string());
-    }
-
-    /// Match a specific input character with Lookahead character
-    ///
-    /// If it does not match, it will panic
-    pub fn match_char(&mut self, x: char) {
-        if self.look != x {
-            expected(&x.to_string());
+    /// Match a specific lookahead token, advancing past it.
+    pub fn match_token(&mut self, expected: &Token) -> Result<(), Diagnostic> {
+        if &self.look != expected {
+            return Err(self.expected(&format!("{:?}", expected)));
         }
-        self.look = self.get_char();
+        self.advance()
     }
 
     /// Get an Identifier
-    pub fn get_name(&mut self) -> char {
-        if !self.look.is_alphabetic() {
-            expected("Name");
+    pub fn get_name(&mut self) -> Result<String, Diagnostic> {
+        match &self.look {
+            Token::Ident(name) => {
+                let name = name.clone();
+                self.advance()?;
+                Ok(name)
+            }
+            _ => Err(self.expected("Name")),
         }
-
-        let look_upcase = self.look.to_ascii_uppercase();
-        self.look = self.get_char();
-
-        look_upcase
     }
 
     /// Get a Number
-    pub fn get_num(&mut self) -> String {
-        if !self.look.is_ascii_digit() {
-            expected("Integer");
-        }
-
-        let mut value = String::new();
-        while self.look.is_ascii_digit() {
-            value.push(self.look);
-            self.look = self.get_char();
+    pub fn get_num(&mut self) -> Result<String, Diagnostic> {
+        match &self.look {
+            Token::Int(value) => {
+                let value = value.clone();
+                self.advance()?;
+                Ok(value)
+            }
+            _ => Err(self.expected("Integer")),
         }
-
-        self.skip_white();
-
-        value
-    }
-
-    /// Output a string with Tab
-    pub fn emit(&mut self, s: &str) {
-        print!("{}", TAB.to_string() + s);
-    }
-
-    /// Output a string with Tab and CRLF
-    pub fn emitln(&mut self, s: &str) {
-        self.emit(s);
-        println!();
     }
 
-    /// Check if lookahead character is Mulop: * or /
-    pub fn is_mulop(&mut self) -> bool {
-        let ops = vec![Ops::DIV, Ops::MUL];
-        ops.iter().any(|op| *op == Ops::from(self.look))
+    /// Check if lookahead token is Mulop: * or /
+    pub fn is_mulop(&self) -> bool {
+        matches!(self.look, Token::Op(Op::Mul) | Token::Op(Op::Div))
     }
 
-    /// Check if lookahead character is Addop: + or -
-    pub fn is_addop(&mut self) -> bool {
-        let ops = vec![Ops::ADD, Ops::SUB];
-        ops.iter().any(|val| *val == Ops::from(self.look))
+    /// Check if lookahead token is Addop: + or -
+    pub fn is_addop(&self) -> bool {
+        matches!(self.look, Token::Op(Op::Add) | Token::Op(Op::Sub))
     }
 
-    /// Parse and Translate a Math Expression
+    /// Parse a Math Expression
     ///
     ///         1+2
     /// or      4-3
@@ -151,17 +102,32 @@ impl<R: BufRead> Cradle<R> {
     ///
     /// <expression> ::= <term> [<addop> <term>]*
     ///
-    pub fn expression(&mut self) {
-        self.emitln("<expr>");
+    pub fn expression(&mut self) -> Result<Expr, Diagnostic> {
+        let mut left = if self.is_addop() {
+            Expr::Num(0)
+        } else {
+            self.term()?
+        };
+        while self.is_addop() {
+            match self.look {
+                Token::Op(Op::Add) => {
+                    left = Expr::Binary(BinOp::Add, Box::new(left), Box::new(self.add()?))
+                }
+                Token::Op(Op::Sub) => {
+                    left = Expr::Binary(BinOp::Sub, Box::new(left), Box::new(self.subtract()?))
+                }
+                _ => return Err(self.expected("Addop")),
+            }
+        }
+        Ok(left)
     }
 
-    /// Parse and Translate an Assignment statement
-    pub fn assignment(&mut self) {
-        let name = self.get_name();
-        self.match_char('=');
-        self.expression();
-        self.emitln(&format!("LEA {}(PC),A0", name));
-        self.emitln("MOVE D0,(A0)");
+    /// Parse an Assignment statement
+    pub fn assignment(&mut self) -> Result<Stmt, Diagnostic> {
+        let name = self.get_name()?;
+        self.match_token(&Token::Assign)?;
+        let expr = self.expression()?;
+        Ok(Stmt::Assign(name, expr))
     }
 
     /// Represent <term>
@@ -169,22 +135,20 @@ impl<R: BufRead> Cradle<R> {
     /// <mulop> -> *, /
     ///
     /// <term> ::= <factor> [<mulop> <factor>]*
-    pub fn term(&mut self) {
-        self.factor();
+    pub fn term(&mut self) -> Result<Expr, Diagnostic> {
+        let mut left = self.factor()?;
         while self.is_mulop() {
-            self.emitln("MOVE D0,-(SP)");
-            match Ops::from(self.look) {
-                Ops::MUL => {
-                    self.multiply();
-                }
-                Ops::DIV => {
-                    self.divide();
+            match self.look {
+                Token::Op(Op::Mul) => {
+                    left = Expr::Binary(BinOp::Mul, Box::new(left), Box::new(self.multiply()?))
                 }
-                _ => {
-                    expected("Mulop");
+                Token::Op(Op::Div) => {
+                    left = Expr::Binary(BinOp::Div, Box::new(left), Box::new(self.divide()?))
                 }
+                _ => return Err(self.expected("Mulop")),
             }
         }
+        Ok(left)
     }
 
     /// Represent <factor>
@@ -195,228 +159,338 @@ impl<R: BufRead> Cradle<R> {
     ///
     /// We can support variables also, i.e b * b + 4 * a * c:
     /// <factor> ::= <number> | (<expression>) | <variable>
-    pub fn factor(&mut self) {
-        if self.look == '(' {
-            self.match_char('(');
-            self.expression();
-            self.match_char(')');
-        } else if self.look.is_alphabetic() {
-            self.ident();
-        } else {
-            let num = self.get_num();
-            self.emitln(&format!("MOVE #{},D0", num));
+    pub fn factor(&mut self) -> Result<Expr, Diagnostic> {
+        match self.look {
+            Token::LParen => {
+                self.advance()?;
+                let expr = self.expression()?;
+                self.match_token(&Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Ident(_) => self.ident(),
+            _ => {
+                let num = self.get_num()?;
+                let n = num
+                    .parse()
+                    .map_err(|_| self.expected("a valid integer"))?;
+                Ok(Expr::Num(n))
+            }
         }
     }
 
     /// Deal with variable and function calls
-    pub fn ident(&mut self) {
-        let name = self.get_name();
-        if self.look == '(' {
-            self.match_char('(');
-            self.match_char(')');
-            self.emitln(&format!("BSR {}", name));
+    pub fn ident(&mut self) -> Result<Expr, Diagnostic> {
+        let name = self.get_name()?;
+        if self.look == Token::LParen {
+            self.advance()?;
+            self.match_token(&Token::RParen)?;
+            Ok(Expr::Call(name))
         } else {
-            self.emitln(&format!("MOVE {}(PC),D0", name));
+            Ok(Expr::Var(name))
         }
     }
 
-    /// Recognize and Translate Multiply
-    pub fn multiply(&mut self) {
-        self.match_char('*');
-        self.factor();
-        self.emitln("MULS (SP)+,D0");
+    /// Recognize Multiply and parse its right-hand <factor>
+    pub fn multiply(&mut self) -> Result<Expr, Diagnostic> {
+        self.match_token(&Token::Op(Op::Mul))?;
+        self.factor()
     }
 
-    /// Recognize and Translate Divide
-    pub fn divide(&mut self) {
-        self.match_char('/');
-        self.factor();
-        self.emitln("MOVE (SP)+,D1");
-        self.emitln("DIVS D1,D0");
+    /// Recognize Divide and parse its right-hand <factor>
+    pub fn divide(&mut self) -> Result<Expr, Diagnostic> {
+        self.match_token(&Token::Op(Op::Div))?;
+        self.factor()
     }
 
-    /// Recognize and Translate Add
-    pub fn add(&mut self) {
-        self.match_char('+');
-        self.term();
-        self.emitln("ADD (SP)+,D0");
+    /// Recognize Add and parse its right-hand <term>
+    pub fn add(&mut self) -> Result<Expr, Diagnostic> {
+        self.match_token(&Token::Op(Op::Add))?;
+        self.term()
     }
 
-    /// Recognize and Translate Subtract
-    pub fn subtract(&mut self) {
-        self.match_char('-');
-        self.term();
-        self.emitln("SUB (SP)+,D0");
-        self.emitln("NEG D0");
+    /// Recognize Subtract and parse its right-hand <term>
+    pub fn subtract(&mut self) -> Result<Expr, Diagnostic> {
+        self.match_token(&Token::Op(Op::Sub))?;
+        self.term()
     }
 
-    /// Parse and Translate a Program
-    pub fn do_program(&mut self) {
-        self.block("");
-        if self.look != 'e' {
-            expected("End");
+    /// Parse a Program
+    pub fn do_program(&mut self) -> Result<Stmt, Diagnostic> {
+        let body = self.block(false)?;
+        if self.look != Token::Kw(Keyword::End) {
+            return Err(self.expected("End"));
         }
-        self.emitln("END");
+        Ok(body)
     }
 
-    /// Recognize and Translate a Statement Block
-    pub fn block(&mut self, label: &str) {
-        while !['e', 'l', 'u'].iter().any(|c| *c == self.look) {
-            match self.look {
-                'i' => self.do_if(&label),
-                'w' => self.do_while(),
-                'p' => self.do_loop(),
-                'r' => self.do_repeat(),
-                'f' => self.do_for(),
-                'd' => self.do_do(),
-                'b' => self.do_break(label),
-                _ => self.other(),
-            }
+    /// Recognize a Statement Block
+    ///
+    /// `in_loop` records whether this block is nested inside a loop
+    /// construct, which is all `do_break` needs to know to accept a
+    /// `BREAK` (real label generation happens in codegen).
+    pub fn block(&mut self, in_loop: bool) -> Result<Stmt, Diagnostic> {
+        let mut stmts = Vec::new();
+        while !matches!(
+            self.look,
+            Token::Kw(Keyword::End) | Token::Kw(Keyword::Else) | Token::Kw(Keyword::Until)
+        ) {
+            let stmt = match self.look {
+                Token::Kw(Keyword::If) => self.do_if(in_loop)?,
+                Token::Kw(Keyword::While) => self.do_while()?,
+                Token::Kw(Keyword::Loop) => self.do_loop()?,
+                Token::Kw(Keyword::Repeat) => self.do_repeat()?,
+                Token::Kw(Keyword::For) => self.do_for()?,
+                Token::Kw(Keyword::Do) => self.do_do()?,
+                Token::Kw(Keyword::Break) => self.do_break(in_loop)?,
+                Token::Ident(_) => self.ident_stmt()?,
+                _ => self.other()?,
+            };
+            stmts.push(stmt);
+        }
+        Ok(Stmt::Block(stmts))
+    }
+
+    /// Parse a Boolean Condition
+    ///
+    /// <bool-expr>  ::= <bool-term> [OR <bool-term>]*
+    pub fn condition(&mut self) -> Result<Expr, Diagnostic> {
+        let mut left = self.bool_term()?;
+        while self.look == Token::Kw(Keyword::Or) {
+            self.advance()?;
+            let right = self.bool_term()?;
+            left = Expr::Logical(LogicOp::Or, Box::new(left), Box::new(right));
         }
+        Ok(left)
     }
 
-    /// Parse and Translate a Boolean Condition
-    pub fn condition(&mut self) {
-        self.emitln("<condition>");
+    /// <bool-term> ::= <not-factor> [AND <not-factor>]*
+    fn bool_term(&mut self) -> Result<Expr, Diagnostic> {
+        let mut left = self.not_factor()?;
+        while self.look == Token::Kw(Keyword::And) {
+            self.advance()?;
+            let right = self.not_factor()?;
+            left = Expr::Logical(LogicOp::And, Box::new(left), Box::new(right));
+        }
+        Ok(left)
     }
 
-    /// Parse and Translate a BREAK
-    pub fn do_break(&mut self, label: &str) {
-        self.match_char('b');
-        if label != "" {
-            self.emitln(&format!("BRA {}", label));
+    /// <not-factor> ::= [!] <relation>
+    fn not_factor(&mut self) -> Result<Expr, Diagnostic> {
+        if self.look == Token::Not {
+            self.advance()?;
+            Ok(Expr::Unary(UnaryOp::Not, Box::new(self.relation()?)))
         } else {
-            panic!("No loop to break from");
+            self.relation()
         }
     }
 
-    /// Recognize and Translate an IF Construct
-    pub fn do_if(&mut self, label: &str) {
-        self.match_char('i');
-        let label1 = self.new_label();
-        let mut label2 = label1.to_string();
-        self.condition();
-        self.emitln(&format!("BEQ {}", &label1));
-        self.block(label);
-        if self.look == 'l' {
-            self.match_char('l');
-            label2 = self.new_label();
-            self.emitln(&format!("BRA {}", label2));
-            self.post_label(&label1);
-            self.block(label);
+    /// <relation> ::= <expression> [<relop> <expression>]
+    fn relation(&mut self) -> Result<Expr, Diagnostic> {
+        let left = self.expression()?;
+        if self.is_relop() {
+            let op = self.relop()?;
+            let right = self.expression()?;
+            Ok(Expr::Relation(op, Box::new(left), Box::new(right)))
+        } else {
+            Ok(left)
         }
-        self.match_char('e');
-        self.post_label(&label2);
-    }
-
-    /// Recognize and Translate a WHILE Statement
-    pub fn do_while(&mut self) {
-        self.match_char('w');
-        let l1 = self.new_label();
-        let l2 = self.new_label();
-        self.post_label(&l1);
-        self.condition();
-        self.emitln(&format!("BEQ {}", l2));
-        self.block(&l2);
-        self.match_char('e');
-        self.emitln(&format!("BRA {}", l1));
-        self.post_label(&l2);
-    }
-
-    /// Parse and Translate a LOOP Statement
-    pub fn do_loop(&mut self) {
-        self.match_char('p');
-        let l1 = self.new_label();
-        let l2 = self.new_label();
-        self.post_label(&l1);
-        self.block(&l2);
-        self.match_char('e');
-        self.emitln(&format!("BRA {}", &l1));
-        self.post_label(&l2);
-    }
-
-    /// Parse and Translate a REPEAT Statement
-    pub fn do_repeat(&mut self) {
-        self.match_char('r');
-        let l1 = self.new_label();
-        let l2 = self.new_label();
-        self.post_label(&l1);
-        self.block(&l2);
-        self.match_char('u');
-        self.condition();
-        self.emitln(&format!("BEQ {}", l1));
-        self.post_label(&l2);
-    }
-
-    /// Parse and Translate a FOR statement
-    pub fn do_for(&mut self) {
-        self.match_char('f');
-        let l1 = self.new_label();
-        let l2 = self.new_label();
-        let name = self.get_name();
-        self.match_char('=');
-        self.expression();
-        self.emitln("SUBQ #1,D0");
-        self.emitln(&format!("LEA {}(PC),A0", name));
-        self.emitln("MOVE DO,(A0)");
-        self.expression();
-        self.emitln("MOVE DO,-(SP)");
-        self.post_label(&l1);
-        self.emitln(&format!("LEA {}(PC),A0", name));
-        self.emitln("MOVE (A0),D0");
-        self.emitln("MOVE #1,D0");
-        self.emitln("MOVE DO,(A0)");
-        self.emitln("CMP (SP),(A0)");
-        self.emitln(&format!("BGT {}", l2));
-        self.block(&l2);
-        self.match_char('e');
-        self.emitln(&format!("BRA {}", l1));
-        self.post_label(&l2);
-        self.emitln("ADDQ #2,SP");
-    }
-
-    /// Parse and Translate a DO Statement
-    pub fn do_do(&mut self) {
-        self.match_char('d');
-        let l1 = self.new_label();
-        let l2 = self.new_label();
-        self.expression();
-        self.emitln("SUBQ #1,D0");
-        self.post_label(&l1);
-        self.emitln("MOVE D0,-(SP)");
-        self.block(&l2);
-        self.emitln("MOVE (SP)+,D0");
-        self.emitln(&format!("DBRA D0,{}", l1));
-        self.emitln("SUBQ #2,SP");
-        self.post_label(&l2);
-        self.emitln("ADDQ #2,SP");
-    }
-
-    /// Generate a Unique Label
-    pub fn new_label(&mut self) -> String {
-        let label = format!("L{}", &usize::to_string(&self.lcount));
-        self.lcount += 1;
-        label
-    }
-
-    /// Post a label to Output
-    pub fn post_label(&mut self, label: &str) {
-        println!("{}:", label);
     }
-}
 
-pub fn expected(x: &str) {
-    panic!("{} Expected", x);
+    fn is_relop(&self) -> bool {
+        matches!(
+            self.look,
+            Token::Assign | Token::Ne | Token::Lt | Token::Gt | Token::Le | Token::Ge
+        )
+    }
+
+    /// <relop> -> =, #, <, >, <=, >=
+    fn relop(&mut self) -> Result<RelOp, Diagnostic> {
+        let op = match self.look {
+            Token::Assign => RelOp::Eq,
+            Token::Ne => RelOp::Ne,
+            Token::Lt => RelOp::Lt,
+            Token::Gt => RelOp::Gt,
+            Token::Le => RelOp::Le,
+            Token::Ge => RelOp::Ge,
+            _ => return Err(self.expected("Relop")),
+        };
+        self.advance()?;
+        Ok(op)
+    }
+
+    /// Parse a BREAK
+    pub fn do_break(&mut self, in_loop: bool) -> Result<Stmt, Diagnostic> {
+        self.match_token(&Token::Kw(Keyword::Break))?;
+        if in_loop {
+            Ok(Stmt::Break)
+        } else {
+            Err(self.expected("a loop to break from"))
+        }
+    }
+
+    /// Recognize and parse an IF Construct
+    pub fn do_if(&mut self, in_loop: bool) -> Result<Stmt, Diagnostic> {
+        self.match_token(&Token::Kw(Keyword::If))?;
+        let cond = self.condition()?;
+        let then_block = self.block(in_loop)?;
+        let else_block = if self.look == Token::Kw(Keyword::Else) {
+            self.advance()?;
+            Some(Box::new(self.block(in_loop)?))
+        } else {
+            None
+        };
+        self.match_token(&Token::Kw(Keyword::End))?;
+        Ok(Stmt::If(cond, Box::new(then_block), else_block))
+    }
+
+    /// Parse a WHILE Statement
+    pub fn do_while(&mut self) -> Result<Stmt, Diagnostic> {
+        self.match_token(&Token::Kw(Keyword::While))?;
+        let cond = self.condition()?;
+        let body = self.block(true)?;
+        self.match_token(&Token::Kw(Keyword::End))?;
+        Ok(Stmt::While(cond, Box::new(body)))
+    }
+
+    /// Parse a LOOP Statement
+    pub fn do_loop(&mut self) -> Result<Stmt, Diagnostic> {
+        self.match_token(&Token::Kw(Keyword::Loop))?;
+        let body = self.block(true)?;
+        self.match_token(&Token::Kw(Keyword::End))?;
+        Ok(Stmt::Loop(Box::new(body)))
+    }
+
+    /// Parse a REPEAT Statement
+    pub fn do_repeat(&mut self) -> Result<Stmt, Diagnostic> {
+        self.match_token(&Token::Kw(Keyword::Repeat))?;
+        let body = self.block(true)?;
+        self.match_token(&Token::Kw(Keyword::Until))?;
+        let cond = self.condition()?;
+        Ok(Stmt::Repeat(Box::new(body), cond))
+    }
+
+    /// Parse a FOR statement
+    pub fn do_for(&mut self) -> Result<Stmt, Diagnostic> {
+        self.match_token(&Token::Kw(Keyword::For))?;
+        let name = self.get_name()?;
+        self.match_token(&Token::Assign)?;
+        let start = self.expression()?;
+        let end = self.expression()?;
+        let body = self.block(true)?;
+        self.match_token(&Token::Kw(Keyword::End))?;
+        Ok(Stmt::For(name, start, end, Box::new(body)))
+    }
+
+    /// Parse a DO Statement
+    pub fn do_do(&mut self) -> Result<Stmt, Diagnostic> {
+        self.match_token(&Token::Kw(Keyword::Do))?;
+        let count = self.expression()?;
+        let body = self.block(true)?;
+        Ok(Stmt::Do(count, Box::new(body)))
+    }
+
+    /// Build a "<what> Expected" diagnostic at the current position.
+    pub fn expected(&self, x: &str) -> Diagnostic {
+        Diagnostic {
+            message: format!("{} Expected", x),
+            line: self.lexer.line,
+            col: self.lexer.col,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bytecode::Compiler;
+    use crate::codegen::{self, Target};
+    use crate::vm::Vm;
+
+    #[test]
+    fn test_eval_sums_a_while_loop_into_an_assigned_variable() {
+        let inp = b"acc = 0 i = 1 while i<=3 acc = acc + i i = i + 1 end end\n";
+        let mut c = Cradle::new(&inp[..]).unwrap();
+        let program = c.do_program().unwrap();
+
+        let mut compiler = Compiler::new();
+        let code = compiler.compile(&program);
+        let mut vm = Vm::new(compiler.slot_count());
+        vm.run(&code);
+
+        let acc = compiler.slot_of("ACC").unwrap();
+        assert_eq!(vm.slot(acc), 6);
+    }
 
     #[test]
     fn test_control_constructs() {
-        let inp = b"afi=xikbeece\n";
-        let mut c = Cradle::new(&inp[..]);
-        c.do_program();
+        let inp = b"a for i = 1 2 break end end\n";
+        let mut c = Cradle::new(&inp[..]).unwrap();
+        let program = c.do_program().unwrap();
+        let mut out = Vec::new();
+        codegen::generate(Target::Mc68000, &program, &mut out);
+        assert!(String::from_utf8(out).unwrap().contains("END"));
+    }
+
+    #[test]
+    fn test_diagnostic_reports_position() {
+        let inp = b"a for i 1 end";
+        let mut c = Cradle::new(&inp[..]).unwrap();
+        let err = c.do_program().unwrap_err();
+        assert_eq!(err.message, "Assign Expected");
+        // The lexer has already scanned `1` and stands on the space after it.
+        assert_eq!((err.line, err.col), (1, 10));
+    }
+
+    #[test]
+    fn test_condition_parses_relational_and_boolean_operators() {
+        let inp = b"a while i<5 AND !i=3 b end end\n";
+        let mut c = Cradle::new(&inp[..]).unwrap();
+        let program = c.do_program().unwrap();
+        let cond = match &program {
+            Stmt::Block(stmts) => match &stmts[1] {
+                Stmt::While(cond, _) => cond.clone(),
+                other => panic!("expected While, got {:?}", other),
+            },
+            other => panic!("expected Block, got {:?}", other),
+        };
+        assert_eq!(
+            cond,
+            Expr::Logical(
+                LogicOp::And,
+                Box::new(Expr::Relation(
+                    RelOp::Lt,
+                    Box::new(Expr::Var("I".to_string())),
+                    Box::new(Expr::Num(5))
+                )),
+                Box::new(Expr::Unary(
+                    UnaryOp::Not,
+                    Box::new(Expr::Relation(
+                        RelOp::Eq,
+                        Box::new(Expr::Var("I".to_string())),
+                        Box::new(Expr::Num(3))
+                    ))
+                ))
+            )
+        );
+        let mut out = Vec::new();
+        codegen::generate(Target::Llvm, &program, &mut out);
+        let llvm_ir = String::from_utf8(out).unwrap();
+        assert!(llvm_ir.contains("icmp slt"));
+        assert!(llvm_ir.contains("icmp eq"));
+        assert!(llvm_ir.contains("xor i64"));
+    }
+
+    #[test]
+    fn test_mc68000_relop_mnemonic_matches_compare_order() {
+        // `CMP (SP)+,D0` computes rhs - lhs, so `i < 5` must set on SGT
+        // (not SLT) to actually test lhs < rhs.
+        let inp = b"a if i<5 x end end\n";
+        let mut c = Cradle::new(&inp[..]).unwrap();
+        let program = c.do_program().unwrap();
+        let mut out = Vec::new();
+        codegen::generate(Target::Mc68000, &program, &mut out);
+        let asm = String::from_utf8(out).unwrap();
+        assert!(asm.contains("SGT D0"));
+        assert!(!asm.contains("SLT D0"));
     }
 }
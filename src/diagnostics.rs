@@ -0,0 +1,20 @@
+//! Parse diagnostics.
+//!
+//! `Cradle`'s parse methods return `Result<_, Diagnostic>` instead of
+//! panicking, so a caller gets a precise "Name Expected at 3:12" instead
+//! of a stack unwind, and so error cases are actually testable.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub message: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.message, self.line, self.col)
+    }
+}
@@ -0,0 +1,195 @@
+//! Multi-character lexer.
+//!
+//! The parser used to conflate lexing and parsing: keywords were single
+//! letters, identifiers were a single character, and `skip_white` was
+//! sprinkled through every parse method. This turns the `Read` stream into
+//! a `Token` stream using maximal-munch scanning instead, so keywords read
+//! as real words (`if`, `while`, `end`), identifiers/numbers are
+//! multi-character, and whitespace is handled once, here.
+
+use std::io::{BufRead, Read};
+
+use crate::diagnostics::Diagnostic;
+
+const TAB: char = '\t';
+const SPACE: char = ' ';
+const NEW_LINE: char = '\n';
+
+/// A reserved word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyword {
+    If,
+    Else,
+    While,
+    Loop,
+    Repeat,
+    Until,
+    For,
+    Do,
+    Break,
+    End,
+    And,
+    Or,
+}
+
+impl Keyword {
+    fn from_ident(s: &str) -> Option<Self> {
+        match s {
+            "IF" => Some(Keyword::If),
+            "ELSE" => Some(Keyword::Else),
+            "WHILE" => Some(Keyword::While),
+            "LOOP" => Some(Keyword::Loop),
+            "REPEAT" => Some(Keyword::Repeat),
+            "UNTIL" => Some(Keyword::Until),
+            "FOR" => Some(Keyword::For),
+            "DO" => Some(Keyword::Do),
+            "BREAK" => Some(Keyword::Break),
+            "END" => Some(Keyword::End),
+            "AND" => Some(Keyword::And),
+            "OR" => Some(Keyword::Or),
+            _ => None,
+        }
+    }
+}
+
+/// An arithmetic operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    Ident(String),
+    Int(String),
+    Kw(Keyword),
+    Op(Op),
+    Assign,
+    /// `#`: not-equal relop.
+    Ne,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    /// `!`: boolean negation.
+    Not,
+    LParen,
+    RParen,
+    Eof,
+}
+
+/// Scans an `R: BufRead` into a `Token` stream, one token at a time.
+pub struct Lexer<R> {
+    look: char,
+    input: R,
+
+    /// Current line of `look`, for diagnostics. 1-based.
+    pub line: usize,
+
+    /// Current column of `look`, for diagnostics. 1-based.
+    pub col: usize,
+}
+
+impl<R: BufRead> Lexer<R> {
+    pub fn new(input: R) -> Self {
+        let mut lexer = Lexer {
+            look: '\0',
+            input,
+            line: 1,
+            col: 0,
+        };
+        lexer.look = lexer.get_char();
+        lexer
+    }
+
+    fn get_char(&mut self) -> char {
+        if self.look == NEW_LINE {
+            self.line += 1;
+            self.col = 0;
+        }
+
+        let c = self
+            .input
+            .by_ref()
+            .bytes()
+            .next()
+            .and_then(|b| b.ok())
+            .map(|b| b as char)
+            .unwrap_or('\0');
+
+        if c != '\0' {
+            self.col += 1;
+        }
+
+        c
+    }
+
+    fn skip_white(&mut self) {
+        while self.look == TAB || self.look == SPACE || self.look == NEW_LINE {
+            self.look = self.get_char();
+        }
+    }
+
+    fn expected(&self, x: &str) -> Diagnostic {
+        Diagnostic {
+            message: format!("{} Expected", x),
+            line: self.line,
+            col: self.col,
+        }
+    }
+
+    /// Scan and return the next token, skipping leading whitespace.
+    pub fn next_token(&mut self) -> Result<Token, Diagnostic> {
+        self.skip_white();
+
+        if self.look.is_ascii_digit() {
+            let mut value = String::new();
+            while self.look.is_ascii_digit() {
+                value.push(self.look);
+                self.look = self.get_char();
+            }
+            return Ok(Token::Int(value));
+        }
+
+        if self.look.is_alphabetic() {
+            let mut name = String::new();
+            while self.look.is_alphanumeric() {
+                name.push(self.look.to_ascii_uppercase());
+                self.look = self.get_char();
+            }
+            return Ok(match Keyword::from_ident(&name) {
+                Some(kw) => Token::Kw(kw),
+                None => Token::Ident(name),
+            });
+        }
+
+        if self.look == '<' || self.look == '>' {
+            let is_lt = self.look == '<';
+            self.look = self.get_char();
+            if self.look == '=' {
+                self.look = self.get_char();
+                return Ok(if is_lt { Token::Le } else { Token::Ge });
+            }
+            return Ok(if is_lt { Token::Lt } else { Token::Gt });
+        }
+
+        let token = match self.look {
+            '\0' => return Ok(Token::Eof),
+            '=' => Token::Assign,
+            '#' => Token::Ne,
+            '!' => Token::Not,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            _ => return Err(self.expected("Token")),
+        };
+        self.look = self.get_char();
+        Ok(token)
+    }
+}
@@ -0,0 +1,18 @@
+//! `maria` parses a tiny Basic-like language into an `ast` tree and either
+//! evaluates it (`vm::eval`) or emits code for it (`codegen::generate`).
+//!
+//! `bin/maria.rs` is the only consumer of this crate root; everything else
+//! is organized as the pipeline stages it's built from: `lexer` scans
+//! source into `Token`s, `controls::Cradle` parses those into an `ast`,
+//! and `bytecode`/`codegen` each lower the `ast` to something runnable.
+
+pub mod ast;
+pub mod bytecode;
+pub mod codegen;
+pub mod controls;
+pub mod diagnostics;
+pub mod lexer;
+pub mod vm;
+
+pub use controls::Cradle;
+pub use diagnostics::Diagnostic;
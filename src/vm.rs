@@ -0,0 +1,162 @@
+//! A small stack machine that executes `bytecode::Instr` programs.
+
+use crate::ast::Stmt;
+use crate::bytecode::{Compiler, Instr, RelOp};
+
+/// Operand stack plus a flat array of variable/temporary slots.
+pub struct Vm {
+    stack: Vec<i64>,
+    slots: Vec<i64>,
+}
+
+impl Vm {
+    pub fn new(slot_count: usize) -> Self {
+        Vm {
+            stack: Vec::new(),
+            slots: vec![0; slot_count],
+        }
+    }
+
+    /// Read a variable/temporary slot's current value.
+    ///
+    /// Paired with `Compiler::slot_of`: a caller that wants to know what a
+    /// variable ended up holding (rather than whatever's left on top of
+    /// the stack, which is usually nothing) reads it from here after `run`.
+    pub fn slot(&self, index: usize) -> i64 {
+        self.slots[index]
+    }
+
+    /// Execute `code` until `Ret`, and return the value left on top of the
+    /// operand stack (0 if it's empty).
+    pub fn run(&mut self, code: &[Instr]) -> i64 {
+        let mut pc = 0;
+        while pc < code.len() {
+            match &code[pc] {
+                Instr::PushInt(n) => self.stack.push(*n),
+                Instr::Load(slot) => self.stack.push(self.slots[*slot]),
+                Instr::Store(slot) => {
+                    let v = self.stack.pop().expect("stack underflow");
+                    self.slots[*slot] = v;
+                }
+                Instr::Add => self.binop(|a, b| a + b),
+                Instr::Sub => self.binop(|a, b| a - b),
+                Instr::Mul => self.binop(|a, b| a * b),
+                // Division is the one binop that can fail on user input
+                // (x/0) rather than only on an internal invariant, and
+                // `run` has no Result to report it through, so it yields
+                // 0 instead of crashing the process.
+                Instr::Div => self.binop(|a, b| if b == 0 { 0 } else { a / b }),
+                Instr::Cmp(rel) => {
+                    let b = self.stack.pop().expect("stack underflow");
+                    let a = self.stack.pop().expect("stack underflow");
+                    let result = match rel {
+                        RelOp::Eq => a == b,
+                        RelOp::Ne => a != b,
+                        RelOp::Lt => a < b,
+                        RelOp::Gt => a > b,
+                        RelOp::Le => a <= b,
+                        RelOp::Ge => a >= b,
+                    };
+                    self.stack.push(result as i64);
+                }
+                Instr::And => self.binop(|a, b| ((a != 0) && (b != 0)) as i64),
+                Instr::Or => self.binop(|a, b| ((a != 0) || (b != 0)) as i64),
+                Instr::Not => {
+                    let v = self.stack.pop().expect("stack underflow");
+                    self.stack.push((v == 0) as i64);
+                }
+                Instr::Jump(addr) => {
+                    pc = *addr;
+                    continue;
+                }
+                Instr::JumpUnless(addr) => {
+                    let v = self.stack.pop().expect("stack underflow");
+                    if v == 0 {
+                        pc = *addr;
+                        continue;
+                    }
+                }
+                Instr::Call(addr) => {
+                    pc = *addr;
+                    continue;
+                }
+                Instr::Ret => break,
+            }
+            pc += 1;
+        }
+        self.stack.last().copied().unwrap_or(0)
+    }
+
+    fn binop(&mut self, f: impl Fn(i64, i64) -> i64) {
+        let b = self.stack.pop().expect("stack underflow");
+        let a = self.stack.pop().expect("stack underflow");
+        self.stack.push(f(a, b));
+    }
+}
+
+/// Compile `program` to bytecode and run it, returning the value left on
+/// top of the stack when it halts. This is the evaluating counterpart to
+/// `codegen::generate`.
+pub fn eval(program: &Stmt) -> i64 {
+    let mut compiler = Compiler::new();
+    let code = compiler.compile(program);
+    Vm::new(compiler.slot_count()).run(&code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `acc = 2*3 - 1`, then loop `i` from 1 upward adding it into `acc`
+    /// until `i == 2`, at which point a `Break`-style `Jump` exits the
+    /// loop before the addition for that iteration runs. Exercises
+    /// arithmetic, relational compares, the loop jump pair, and a break.
+    #[test]
+    fn test_run_executes_arithmetic_loop_and_break() {
+        const ACC: usize = 0;
+        const I: usize = 1;
+        let top = 8;
+        let after_break_check = 17;
+        let exit = 26;
+        let code = vec![
+            Instr::PushInt(2),
+            Instr::PushInt(3),
+            Instr::Mul,
+            Instr::PushInt(1),
+            Instr::Sub,
+            Instr::Store(ACC), // acc = 2*3 - 1 = 5
+            Instr::PushInt(1),
+            Instr::Store(I), // i = 1
+            Instr::Load(I),  // loop top
+            Instr::PushInt(4),
+            Instr::Cmp(RelOp::Lt), // i < 4
+            Instr::JumpUnless(exit),
+            Instr::Load(I),
+            Instr::PushInt(2),
+            Instr::Cmp(RelOp::Eq), // i == 2
+            Instr::JumpUnless(after_break_check),
+            Instr::Jump(exit), // break
+            Instr::Load(ACC),
+            Instr::Load(I),
+            Instr::Add,
+            Instr::Store(ACC), // acc += i
+            Instr::Load(I),
+            Instr::PushInt(1),
+            Instr::Add,
+            Instr::Store(I), // i += 1
+            Instr::Jump(top),
+            Instr::Load(ACC), // leave the result on the stack for Ret
+            Instr::Ret,
+        ];
+
+        // i=1: 1<4, 1!=2, acc becomes 5+1=6, i becomes 2.
+        // i=2: 2<4, 2==2 -> break before acc+=2 runs.
+        assert_eq!(Vm::new(2).run(&code), 6);
+    }
+
+    #[test]
+    fn test_run_div_by_zero_yields_zero_instead_of_panicking() {
+        let code = vec![Instr::PushInt(5), Instr::PushInt(0), Instr::Div, Instr::Ret];
+        assert_eq!(Vm::new(0).run(&code), 0);
+    }
+}